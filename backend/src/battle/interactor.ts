@@ -1,4 +1,4 @@
-import { MatchRoom, matches, Attack, playerToMatch } from '../matchmaking/entities.js';
+import { MatchRoom, matches, Attack, playerToMatch, SignedPlacement } from '../matchmaking/entities.js';
 import { removeMatch } from '../matchmaking/interactor.js';
 import { TURN_TIMEOUT_MS } from './entities.js';
 
@@ -6,15 +6,18 @@ export function submitPlacement(
   match: MatchRoom,
   publicKey: string,
   boardHash: string,
-  proof: number[]
+  proof: number[],
+  placement: SignedPlacement
 ): { bothReady: boolean; firstTurn?: string } {
   if (publicKey === match.player1.publicKey) {
     match.player1BoardHash = boardHash;
     match.player1BoardProof = proof;
+    match.player1Placement = placement;
     match.player1Ready = true;
   } else {
     match.player2BoardHash = boardHash;
     match.player2BoardProof = proof;
+    match.player2Placement = placement;
     match.player2Ready = true;
   }
 
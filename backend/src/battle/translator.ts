@@ -17,7 +17,7 @@ import { getShipSizes } from '../matchmaking/entities.js';
 import { proveTurnsProof } from '../turns-proof/interactor.js';
 import { createTurnsProofAdapter } from '../turns-proof/adapter.js';
 import type { ShipTuple, AttackTuple } from '../shared/entities.js';
-import { openMatchOnChain, closeMatchOnChain } from '../soroban/adapter.js';
+import { openMatchOnChain, bindMatchOnChain, registerBoardHashOnChain, closeMatchOnChain } from '../soroban/adapter.js';
 import { getServerPublicKey } from '../payment/stellar-asset.js';
 
 function getOpponentSocketId(
@@ -93,7 +93,10 @@ export function registerBattleHandlers(
       return;
     }
 
-    const result = submitPlacement(match, publicKey, data.boardHash, data.proof);
+    const result = submitPlacement(match, publicKey, data.boardHash, data.proof, {
+      timestamp: data.timestamp,
+      signature: data.signature,
+    });
     const opponentId = getOpponentSocketId(match, publicKey);
     debug('[battle]', `Placement submitted: bothReady=${result.bothReady}, firstTurn=${result.firstTurn}`);
 
@@ -123,6 +126,21 @@ export function registerBattleHandlers(
           proof2: match.player2BoardProof!, pubInputs2,
         }).then(({ sessionId }) => {
           match.sorobanSessionId = sessionId;
+          return bindMatchOnChain({
+            sessionId,
+            matchId: match.id,
+            player1Key: match.player1.publicKey,
+            player2Key: match.player2!.publicKey,
+          }).then(async () => {
+            for (const player1 of [true, false]) {
+              await registerBoardHashOnChain({
+                sessionId,
+                player1,
+                boardHash: player1 ? match.player1BoardHash! : match.player2BoardHash!,
+                placement: player1 ? match.player1Placement! : match.player2Placement!,
+              });
+            }
+          });
         }).catch((err) => {
           console.error(c.cyan('[stellar]') + ` open_match failed: ${c.err(err.message)}`);
         });
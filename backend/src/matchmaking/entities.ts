@@ -11,6 +11,8 @@ export interface MatchRoom {
   player2BoardHash?: string;
   player1BoardProof?: number[];
   player2BoardProof?: number[];
+  player1Placement?: SignedPlacement; // signed placement:ready, for on-chain disputes
+  player2Placement?: SignedPlacement;
   player1Ready: boolean;
   player2Ready: boolean;
   currentTurn?: string;     // publicKey of current turn player
@@ -26,6 +28,11 @@ export interface MatchRoom {
   createdAt: number;
 }
 
+export interface SignedPlacement {
+  timestamp: number;
+  signature: string;
+}
+
 export interface Attack {
   attacker: string;   // publicKey
   row: number;
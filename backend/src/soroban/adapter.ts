@@ -10,6 +10,7 @@ import {
   Address,
 } from '@stellar/stellar-sdk';
 import { getServerKeypair } from '../payment/stellar-asset.js';
+import type { SignedPlacement } from '../matchmaking/entities.js';
 import { c, debug } from '../log.js';
 
 let sorobanServer: rpc.Server | null = null;
@@ -174,6 +175,69 @@ export async function openMatchOnChain(params: OpenMatchParams): Promise<OpenMat
   return { txHash, sessionId };
 }
 
+export interface BindMatchParams {
+  sessionId: number;
+  matchId: string;
+  player1Key: string; // hex Ed25519 key the player signs actions with
+  player2Key: string;
+}
+
+/**
+ * Bind an opened session to its backend match id and the players' signing
+ * keys, so their signed attacks and shot results can be disputed on-chain.
+ */
+export async function bindMatchOnChain(params: BindMatchParams): Promise<string> {
+  const kp = getServerKeypair();
+
+  const args = [
+    nativeToScVal(params.sessionId, { type: 'u32' }),
+    nativeToScVal(params.matchId, { type: 'string' }),
+    nativeToScVal(Buffer.from(params.player1Key, 'hex'), { type: 'bytes' }),
+    nativeToScVal(Buffer.from(params.player2Key, 'hex'), { type: 'bytes' }),
+  ];
+
+  const { txHash } = await buildSignSubmit(kp, 'bind_match', args);
+  return txHash;
+}
+
+export interface RegisterBoardHashParams {
+  sessionId: number;
+  player1: boolean;
+  boardHash: string; // exactly as the player signed it
+  placement: SignedPlacement;
+}
+
+/**
+ * Register a player's board hash from their signed placement:ready, so
+ * their shot results can be disputed with shot proofs on-chain.
+ */
+export async function registerBoardHashOnChain(params: RegisterBoardHashParams): Promise<string> {
+  const kp = getServerKeypair();
+
+  const placement = xdr.ScVal.scvMap([
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('board_hash'),
+      val: nativeToScVal(params.boardHash, { type: 'string' }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('signature'),
+      val: nativeToScVal(Buffer.from(params.placement.signature, 'hex'), { type: 'bytes' }),
+    }),
+    new xdr.ScMapEntry({
+      key: xdr.ScVal.scvSymbol('timestamp'),
+      val: nativeToScVal(params.placement.timestamp, { type: 'u64' }),
+    }),
+  ]);
+  const args = [
+    nativeToScVal(params.sessionId, { type: 'u32' }),
+    nativeToScVal(params.player1, { type: 'bool' }),
+    placement,
+  ];
+
+  const { txHash } = await buildSignSubmit(kp, 'register_board_hash', args);
+  return txHash;
+}
+
 export interface CloseMatchParams {
   sessionId: number;
   proof: number[];
@@ -0,0 +1,505 @@
+//! Commit-reveal matches, for players without a ZK prover.
+//!
+//! Each player stakes `stake` of `token` and commits `board_commitment(ships,
+//! salt)`. Shots and their answers are contract calls authorised by the
+//! player making them, so every answer is signed and recorded. When a board
+//! is sunk (or every cell of it has been shot) both players reveal, and the
+//! contract checks each board in full: fleet sizes, bounds, overlaps, the
+//! commitment, and every recorded answer against the revealed ships. A
+//! player whose reveal fails any check, or who stops answering, shooting or
+//! revealing past the deadline, forfeits the match and their stake to the
+//! opponent; honest players get their stake back.
+
+use soroban_sdk::{
+    contractimpl, contracttype, log, symbol_short, token::TokenClient, Address, Bytes, BytesN, Env,
+    Symbol, Vec,
+};
+
+use crate::{BattleshipContract, BattleshipContractArgs, BattleshipContractClient};
+use crate::{Error, GameHubClient, ADMIN, GAME_HUB, SESSION_CTR};
+
+/// Ledgers a player has to shoot or answer once it is their move (~30 min).
+pub const MOVE_TIMEOUT_LEDGERS: u32 = 360;
+/// Ledgers to commit after the match opens, or to reveal after the game
+/// ends (~1 day).
+pub const REVEAL_TIMEOUT_LEDGERS: u32 = 17_280;
+
+/// Matches hold stakes, so unlike the proof-mode `MatchState` they live in
+/// persistent storage, topped up to about 30 days on every write.
+const MATCH_TTL: u32 = 518_400;
+
+fn match_key(session_id: u32) -> (Symbol, u32) {
+    (symbol_short!("cr_match"), session_id)
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum CommitPhase {
+    Committing = 0,
+    Playing = 1,
+    Revealing = 2,
+    Closed = 3,
+}
+
+/// A ship as the board circuits describe it: top-left cell, length, and
+/// orientation.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ship {
+    pub row: u32,
+    pub col: u32,
+    pub size: u32,
+    pub horizontal: bool,
+}
+
+/// One player's side of a commit-reveal match. `shots` and `hits` are cell
+/// bitmasks over this player's board (bit `row * grid_size + col`): the
+/// cells the opponent has fired at and those this player answered as hits.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitBoard {
+    pub player: Address,
+    pub commitment: Option<BytesN<32>>,
+    pub shots: u128,
+    pub hits: u128,
+    pub revealed: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommitMatch {
+    pub board1: CommitBoard,
+    pub board2: CommitBoard,
+    pub token: Address,
+    pub stake: i128,
+    pub grid_size: u32,
+    pub phase: CommitPhase,
+    /// Who shoots next while playing.
+    pub turn: Address,
+    /// The cell `turn` fired at, awaiting the opponent's answer.
+    pub pending_shot: Option<u32>,
+    /// Last ledger for the move the match is waiting on.
+    pub deadline: u32,
+    /// Whoever sank the other fleet, once the game is over.
+    pub winner: Option<Address>,
+}
+
+/// Ship sizes for a grid, as the backend's `getShipSizes` deals them.
+pub fn fleet(env: &Env, grid_size: u32) -> Option<Vec<u32>> {
+    match grid_size {
+        6 => Some(soroban_sdk::vec![env, 2, 2, 3]),
+        10 => Some(soroban_sdk::vec![env, 5, 4, 3, 3, 2]),
+        _ => None,
+    }
+}
+
+/// What a player commits to: `sha256(board || salt)`, where `board` is
+/// `row, col, size, horizontal` as one byte each for every ship in fleet
+/// order.
+pub fn board_commitment(env: &Env, ships: &Vec<Ship>, salt: &BytesN<32>) -> BytesN<32> {
+    let mut preimage = Bytes::new(env);
+    for ship in ships.iter() {
+        for field in [ship.row, ship.col, ship.size, ship.horizontal as u32] {
+            preimage.push_back(field as u8);
+        }
+    }
+    preimage.append(&salt.clone().into());
+    env.crypto().sha256(&preimage).into()
+}
+
+/// The cells `ships` cover, or `None` if they aren't the fleet for
+/// `grid_size` in order, leave the grid or overlap.
+fn occupied_cells(fleet: &Vec<u32>, grid_size: u32, ships: &Vec<Ship>) -> Option<u128> {
+    if ships.len() != fleet.len() {
+        return None;
+    }
+    let mut occupied = 0u128;
+    for (ship, size) in ships.iter().zip(fleet.iter()) {
+        if ship.size != size {
+            return None;
+        }
+        let (rows, cols) = if ship.horizontal {
+            (1, size)
+        } else {
+            (size, 1)
+        };
+        if ship.row.checked_add(rows)? > grid_size || ship.col.checked_add(cols)? > grid_size {
+            return None;
+        }
+        for i in 0..size {
+            let (row, col) = if ship.horizontal {
+                (ship.row, ship.col + i)
+            } else {
+                (ship.row + i, ship.col)
+            };
+            let cell = 1u128 << (row * grid_size + col);
+            if occupied & cell != 0 {
+                return None;
+            }
+            occupied |= cell;
+        }
+    }
+    Some(occupied)
+}
+
+fn all_cells(grid_size: u32) -> u128 {
+    (1u128 << (grid_size * grid_size)) - 1
+}
+
+fn load(env: &Env, session_id: u32) -> Result<CommitMatch, Error> {
+    env.storage()
+        .persistent()
+        .get(&match_key(session_id))
+        .ok_or(Error::MatchNotFound)
+}
+
+fn save(env: &Env, session_id: u32, state: &CommitMatch) {
+    let key = match_key(session_id);
+    env.storage().persistent().set(&key, state);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, MATCH_TTL, MATCH_TTL);
+}
+
+impl CommitMatch {
+    /// `(own board, opponent's board)` for `player`.
+    fn boards_mut(
+        &mut self,
+        player: &Address,
+    ) -> Result<(&mut CommitBoard, &mut CommitBoard), Error> {
+        if *player == self.board1.player {
+            Ok((&mut self.board1, &mut self.board2))
+        } else if *player == self.board2.player {
+            Ok((&mut self.board2, &mut self.board1))
+        } else {
+            Err(Error::NotAPlayer)
+        }
+    }
+
+    fn expect_phase(&self, phase: CommitPhase) -> Result<(), Error> {
+        if self.phase != phase {
+            return Err(Error::WrongPhase);
+        }
+        Ok(())
+    }
+
+    /// The player the match is waiting on, who forfeits once the deadline
+    /// passes; `None` while committing or revealing if both (or neither)
+    /// have done their part.
+    fn awaited(&self) -> Option<Address> {
+        let pending = |done1: bool, done2: bool| match (done1, done2) {
+            (true, false) => Some(self.board2.player.clone()),
+            (false, true) => Some(self.board1.player.clone()),
+            _ => None,
+        };
+        match self.phase {
+            CommitPhase::Committing => pending(
+                self.board1.commitment.is_some(),
+                self.board2.commitment.is_some(),
+            ),
+            CommitPhase::Playing if self.pending_shot.is_some() => Some(self.opponent(&self.turn)),
+            CommitPhase::Playing => Some(self.turn.clone()),
+            CommitPhase::Revealing => pending(self.board1.revealed, self.board2.revealed),
+            CommitPhase::Closed => None,
+        }
+    }
+
+    fn opponent(&self, player: &Address) -> Address {
+        if *player == self.board1.player {
+            self.board2.player.clone()
+        } else {
+            self.board1.player.clone()
+        }
+    }
+}
+
+/// Pays out and reports `winner` to the Game Hub. With `forfeit`, the loser's
+/// stake goes to the winner; otherwise each player that staked gets theirs
+/// back.
+fn close(env: &Env, session_id: u32, state: &mut CommitMatch, winner: &Address, forfeit: bool) {
+    let token = TokenClient::new(env, &state.token);
+    let contract = env.current_contract_address();
+    if state.stake > 0 {
+        let staked = |board: &CommitBoard| board.commitment.is_some();
+        if forfeit {
+            let pot = state.stake * (staked(&state.board1) as i128 + staked(&state.board2) as i128);
+            token.transfer(&contract, winner, &pot);
+        } else {
+            for board in [&state.board1, &state.board2] {
+                if staked(board) {
+                    token.transfer(&contract, &board.player, &state.stake);
+                }
+            }
+        }
+    }
+    state.phase = CommitPhase::Closed;
+    state.pending_shot = None;
+    state.winner = Some(winner.clone());
+    save(env, session_id, state);
+
+    let game_hub_addr: Address = env.storage().instance().get(&GAME_HUB).unwrap();
+    GameHubClient::new(env, &game_hub_addr)
+        .end_game(&session_id, &(*winner == state.board1.player));
+    log!(
+        env,
+        "Commit match closed: session_id={}, forfeit={}",
+        session_id,
+        forfeit
+    );
+}
+
+#[contractimpl]
+impl BattleshipContract {
+    /// Open a commit-reveal match on a `grid_size` grid (6 or 10) where each
+    /// player stakes `stake` of `token`. Admin-only. Returns session_id;
+    /// both players then have `REVEAL_TIMEOUT_LEDGERS` to `commit_board`.
+    pub fn open_commit_match(
+        env: Env,
+        p1: Address,
+        p2: Address,
+        token: Address,
+        stake: i128,
+        grid_size: u32,
+    ) -> Result<u32, Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotAdmin)?;
+        admin.require_auth();
+        if stake < 0 {
+            return Err(Error::InvalidStake);
+        }
+        fleet(&env, grid_size).ok_or(Error::UnsupportedGrid)?;
+        if p1 == p2 {
+            return Err(Error::NotAPlayer);
+        }
+
+        let session_id: u32 = env.storage().instance().get(&SESSION_CTR).unwrap_or(0u32) + 1;
+        env.storage().instance().set(&SESSION_CTR, &session_id);
+
+        let board = |player: &Address| CommitBoard {
+            player: player.clone(),
+            commitment: None,
+            shots: 0,
+            hits: 0,
+            revealed: false,
+        };
+        let state = CommitMatch {
+            board1: board(&p1),
+            board2: board(&p2),
+            token,
+            stake,
+            grid_size,
+            phase: CommitPhase::Committing,
+            turn: p1.clone(),
+            pending_shot: None,
+            deadline: env.ledger().sequence() + REVEAL_TIMEOUT_LEDGERS,
+            winner: None,
+        };
+        save(&env, session_id, &state);
+
+        let game_hub_addr: Address = env.storage().instance().get(&GAME_HUB).unwrap();
+        let self_addr = env.current_contract_address();
+        GameHubClient::new(&env, &game_hub_addr).start_game(
+            &self_addr,
+            &session_id,
+            &p1,
+            &p2,
+            &0i128,
+            &0i128,
+        );
+
+        log!(&env, "Commit match opened: session_id={}", session_id);
+        Ok(session_id)
+    }
+
+    /// Commit `player`'s board and take their stake. Once both have
+    /// committed, player 1 shoots first.
+    pub fn commit_board(
+        env: Env,
+        session_id: u32,
+        player: Address,
+        commitment: BytesN<32>,
+    ) -> Result<(), Error> {
+        player.require_auth();
+        let mut state = load(&env, session_id)?;
+        state.expect_phase(CommitPhase::Committing)?;
+        let (token, stake) = (state.token.clone(), state.stake);
+        let (own, other) = state.boards_mut(&player)?;
+        if own.commitment.is_some() {
+            return Err(Error::AlreadyCommitted);
+        }
+        own.commitment = Some(commitment);
+        let both = other.commitment.is_some();
+        if stake > 0 {
+            TokenClient::new(&env, &token).transfer(
+                &player,
+                env.current_contract_address(),
+                &stake,
+            );
+        }
+        if both {
+            state.phase = CommitPhase::Playing;
+            state.deadline = env.ledger().sequence() + MOVE_TIMEOUT_LEDGERS;
+        }
+        save(&env, session_id, &state);
+        Ok(())
+    }
+
+    /// Fire at (`row`, `col`) of the opponent's board; it must be
+    /// `attacker`'s turn and the cell not shot before.
+    pub fn fire(
+        env: Env,
+        session_id: u32,
+        attacker: Address,
+        row: u32,
+        col: u32,
+    ) -> Result<(), Error> {
+        attacker.require_auth();
+        let mut state = load(&env, session_id)?;
+        state.expect_phase(CommitPhase::Playing)?;
+        state.boards_mut(&attacker)?;
+        if state.turn != attacker {
+            return Err(Error::NotYourTurn);
+        }
+        if state.pending_shot.is_some() {
+            return Err(Error::ShotPending);
+        }
+        let grid_size = state.grid_size;
+        if row >= grid_size || col >= grid_size {
+            return Err(Error::CellOutOfBounds);
+        }
+        let cell = row * grid_size + col;
+        let (_, target) = state.boards_mut(&attacker)?;
+        if target.shots & (1u128 << cell) != 0 {
+            return Err(Error::CellAlreadyShot);
+        }
+        state.pending_shot = Some(cell);
+        state.deadline = env.ledger().sequence() + MOVE_TIMEOUT_LEDGERS;
+        save(&env, session_id, &state);
+        Ok(())
+    }
+
+    /// Answer the pending shot at `defender`'s board. The answer is recorded
+    /// and checked against the board at reveal. Ends the game once all of
+    /// `defender`'s ship cells are hit, or every cell of the board has been
+    /// shot; otherwise it is `defender`'s turn to shoot.
+    pub fn answer_shot(
+        env: Env,
+        session_id: u32,
+        defender: Address,
+        hit: bool,
+    ) -> Result<(), Error> {
+        defender.require_auth();
+        let mut state = load(&env, session_id)?;
+        state.expect_phase(CommitPhase::Playing)?;
+        let cell = state.pending_shot.ok_or(Error::NoPendingShot)?;
+        if state.turn == defender {
+            return Err(Error::NotYourTurn);
+        }
+        let grid_size = state.grid_size;
+        let fleet_cells: u32 = fleet(&env, grid_size).unwrap().iter().sum();
+        let (own, _) = state.boards_mut(&defender)?;
+        own.shots |= 1u128 << cell;
+        if hit {
+            own.hits |= 1u128 << cell;
+        }
+        let game_over = own.hits.count_ones() == fleet_cells || own.shots == all_cells(grid_size);
+        let sunk = own.hits.count_ones() == fleet_cells;
+
+        state.pending_shot = None;
+        if game_over {
+            if sunk {
+                state.winner = Some(state.turn.clone());
+            }
+            state.phase = CommitPhase::Revealing;
+            state.deadline = env.ledger().sequence() + REVEAL_TIMEOUT_LEDGERS;
+        } else {
+            state.turn = defender;
+            state.deadline = env.ledger().sequence() + MOVE_TIMEOUT_LEDGERS;
+        }
+        save(&env, session_id, &state);
+        Ok(())
+    }
+
+    /// Reveal `player`'s board after the game. A board that isn't the fleet,
+    /// leaves the grid, overlaps, doesn't match the commitment, or
+    /// contradicts any of `player`'s answers forfeits the match and the
+    /// stake to the opponent. Once both boards check out the stakes are
+    /// returned and the winner of the game is reported.
+    pub fn reveal_board(
+        env: Env,
+        session_id: u32,
+        player: Address,
+        ships: Vec<Ship>,
+        salt: BytesN<32>,
+    ) -> Result<bool, Error> {
+        player.require_auth();
+        let mut state = load(&env, session_id)?;
+        state.expect_phase(CommitPhase::Revealing)?;
+        let fleet = fleet(&env, state.grid_size).unwrap();
+        let grid_size = state.grid_size;
+        let (own, other) = state.boards_mut(&player)?;
+        if own.revealed {
+            return Err(Error::AlreadyRevealed);
+        }
+        let honest = occupied_cells(&fleet, grid_size, &ships).is_some_and(|occupied| {
+            own.commitment == Some(board_commitment(&env, &ships, &salt))
+                && own.shots & occupied == own.hits
+        });
+        if !honest {
+            let opponent = other.player.clone();
+            log!(
+                &env,
+                "Commit match reveal rejected: session_id={}",
+                session_id
+            );
+            close(&env, session_id, &mut state, &opponent, true);
+            return Ok(false);
+        }
+        own.revealed = true;
+        let both = other.revealed;
+        if both {
+            // Two honest boards can only end the game by one being sunk.
+            let winner = state.winner.clone().unwrap();
+            close(&env, session_id, &mut state, &winner, false);
+        } else {
+            save(&env, session_id, &state);
+        }
+        Ok(true)
+    }
+
+    /// End a match whose deadline has passed in favour of `claimant`: the
+    /// player it was waiting on (to shoot, answer or reveal) forfeits. A
+    /// match the opponent never committed to refunds the claimant, and one
+    /// neither player revealed refunds both and reports the game's winner.
+    pub fn claim_timeout(env: Env, session_id: u32, claimant: Address) -> Result<(), Error> {
+        claimant.require_auth();
+        let mut state = load(&env, session_id)?;
+        state.boards_mut(&claimant)?;
+        if state.phase == CommitPhase::Closed {
+            return Err(Error::MatchAlreadyClosed);
+        }
+        if env.ledger().sequence() <= state.deadline {
+            return Err(Error::DeadlineNotReached);
+        }
+        match (state.awaited(), state.phase) {
+            (Some(awaited), phase) if awaited != claimant => {
+                let forfeit = phase != CommitPhase::Committing;
+                close(&env, session_id, &mut state, &claimant, forfeit);
+            }
+            (None, CommitPhase::Revealing) if !state.board1.revealed => {
+                let winner = state.winner.clone().unwrap_or(claimant);
+                close(&env, session_id, &mut state, &winner, false);
+            }
+            _ => return Err(Error::NothingToClaim),
+        }
+        Ok(())
+    }
+
+    pub fn get_commit_match(env: Env, session_id: u32) -> Result<CommitMatch, Error> {
+        load(&env, session_id)
+    }
+}
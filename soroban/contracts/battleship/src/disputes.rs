@@ -0,0 +1,589 @@
+//! Disputes over the messages players sign for the backend.
+//!
+//! Every `battle:attack` and `battle:shot_result` a player sends is signed
+//! with their Ed25519 key over `{publicKeyHex}:{action}:{json}:{timestamp}`,
+//! the message `verifyAction` checks in the backend. Once the admin binds a
+//! session to its backend match id and the players' keys, anyone holding
+//! those messages can hold a player to them here, without the backend:
+//!
+//! - two shot results for the same cell with different answers end the
+//!   match against the player who signed both (`dispute_contradiction`);
+//! - an attack the defender never answered can be challenged, and unless a
+//!   signed answer for that cell is posted within `ANSWER_TIMEOUT_LEDGERS`
+//!   the attacker wins (`challenge_unanswered`, `answer_challenge`,
+//!   `claim_unanswered`). Turns alternate, player 1 first, so the attack a
+//!   defender owes an answer to is the one after their own last attack:
+//!   a challenge names that attack, and each one can be challenged once;
+//! - a `placement:ready` fixes the board hash a player's shot proofs are
+//!   checked against (`register_board_hash`), and a shot proof for that
+//!   board showing the opposite of a signed result ends the match against
+//!   its signer (`dispute_shot`).
+
+use soroban_sdk::{
+    contractimpl, contracttype, log, symbol_short, Address, Bytes, BytesN, Env, String, Symbol,
+};
+
+use crate::{BattleshipContract, BattleshipContractArgs, BattleshipContractClient};
+use crate::{Error, GameHubClient, MatchState, VerifierClient, ADMIN, GAME_HUB, VERIFIER};
+
+/// Ledgers a defender has to answer a challenged attack (~1 day), so an
+/// answer doesn't depend on watching the chain.
+pub const ANSWER_TIMEOUT_LEDGERS: u32 = 17_280;
+/// Longest backend match id accepted by `bind_match`.
+pub const MAX_MATCH_ID_LEN: u32 = 64;
+/// Longest `boardHash` string: `0x` and 64 hex digits.
+const MAX_BOARD_HASH_LEN: u32 = 66;
+/// `shot_proof`'s public inputs: board hash, row, col, is_hit.
+const SHOT_PUBLIC_INPUTS_LEN: u32 = 4 * 32;
+
+/// Same lifetime as the `MatchState` the binding belongs to.
+const MATCH_TTL: u32 = 518_400;
+
+fn binding_key(session_id: u32) -> (Symbol, u32) {
+    (symbol_short!("binding"), session_id)
+}
+
+fn challenge_key(session_id: u32) -> (Symbol, u32) {
+    (symbol_short!("challenge"), session_id)
+}
+
+/// Earliest timestamp of the defender's own attack that the next challenge
+/// against them may follow; absent until they are first challenged.
+fn turn_key(session_id: u32, defender_is_player1: bool) -> (Symbol, u32, bool) {
+    (symbol_short!("turn"), session_id, defender_is_player1)
+}
+
+/// What ties an on-chain session to the backend's match: its id, and the
+/// keys each player signs their actions with (the hex `publicKey` of their
+/// socket session, as raw bytes). The board hashes are filled in by
+/// `register_board_hash`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatchBinding {
+    pub match_id: Bytes,
+    pub player1_key: BytesN<32>,
+    pub player2_key: BytesN<32>,
+    pub player1_board: Option<BytesN<32>>,
+    pub player2_board: Option<BytesN<32>>,
+}
+
+/// A signed `placement:ready`. `board_hash` is the string exactly as signed;
+/// the Poseidon2 hash as hex, `0x` optional.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedPlacement {
+    pub board_hash: String,
+    pub timestamp: u64,
+    pub signature: BytesN<64>,
+}
+
+/// A signed `battle:attack`: the fields of `AttackPayload` minus `matchId`,
+/// which comes from the binding.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedAttack {
+    pub row: u32,
+    pub col: u32,
+    pub timestamp: u64,
+    pub signature: BytesN<64>,
+}
+
+/// A signed `battle:shot_result`, `hit` standing for `result: 'hit'`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignedShotResult {
+    pub row: u32,
+    pub col: u32,
+    pub hit: bool,
+    pub timestamp: u64,
+    pub signature: BytesN<64>,
+}
+
+/// An attack waiting for the defender's signed answer until `deadline`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Challenge {
+    pub attacker_is_player1: bool,
+    pub row: u32,
+    pub col: u32,
+    pub deadline: u32,
+}
+
+// ─── Signed messages ───
+
+pub(crate) fn load_binding(env: &Env, session_id: u32) -> Result<MatchBinding, Error> {
+    env.storage()
+        .temporary()
+        .get(&binding_key(session_id))
+        .ok_or(Error::MatchNotBound)
+}
+
+impl MatchBinding {
+    pub(crate) fn key(&self, player1: bool) -> &BytesN<32> {
+        if player1 {
+            &self.player1_key
+        } else {
+            &self.player2_key
+        }
+    }
+
+    /// Panics (failing the call) unless `signature` is `key`'s signature of
+    /// `action` with `json` as its data.
+    fn verify(
+        &self,
+        env: &Env,
+        key: &BytesN<32>,
+        action: &str,
+        json: &Bytes,
+        timestamp: u64,
+        signature: &BytesN<64>,
+    ) {
+        let mut message = Bytes::new(env);
+        for byte in key.to_array() {
+            push_hex(&mut message, byte);
+        }
+        message.extend_from_slice(b":");
+        message.extend_from_slice(action.as_bytes());
+        message.extend_from_slice(b":");
+        message.append(json);
+        message.extend_from_slice(b":");
+        push_decimal(&mut message, timestamp);
+        env.crypto().ed25519_verify(key, &message, signature);
+    }
+
+    /// `JSON.stringify({ matchId, row, col })`, the prefix of both payloads.
+    fn cell_json(&self, env: &Env, row: u32, col: u32) -> Bytes {
+        let mut json = Bytes::from_slice(env, br#"{"matchId":""#);
+        json.append(&self.match_id);
+        json.extend_from_slice(br#"","row":"#);
+        push_decimal(&mut json, row.into());
+        json.extend_from_slice(br#","col":"#);
+        push_decimal(&mut json, col.into());
+        json
+    }
+
+    pub(crate) fn verify_attack(
+        &self,
+        env: &Env,
+        attacker_is_player1: bool,
+        attack: &SignedAttack,
+    ) {
+        let mut json = self.cell_json(env, attack.row, attack.col);
+        json.extend_from_slice(b"}");
+        let key = self.key(attacker_is_player1);
+        self.verify(
+            env,
+            key,
+            "battle:attack",
+            &json,
+            attack.timestamp,
+            &attack.signature,
+        );
+    }
+
+    pub(crate) fn verify_shot_result(
+        &self,
+        env: &Env,
+        defender_is_player1: bool,
+        result: &SignedShotResult,
+    ) {
+        let mut json = self.cell_json(env, result.row, result.col);
+        json.extend_from_slice(if result.hit {
+            br#","result":"hit"}"#.as_slice()
+        } else {
+            br#","result":"miss"}"#.as_slice()
+        });
+        let key = self.key(defender_is_player1);
+        self.verify(
+            env,
+            key,
+            "battle:shot_result",
+            &json,
+            result.timestamp,
+            &result.signature,
+        );
+    }
+
+    /// The board hash `placement` commits to, once its signature checks out.
+    pub(crate) fn verify_placement(
+        &self,
+        env: &Env,
+        player1: bool,
+        placement: &SignedPlacement,
+    ) -> Result<BytesN<32>, Error> {
+        let (text, board_hash) =
+            parse_board_hash(env, &placement.board_hash).ok_or(Error::InvalidBoardHash)?;
+        let mut json = Bytes::from_slice(env, br#"{"matchId":""#);
+        json.append(&self.match_id);
+        json.extend_from_slice(br#"","boardHash":""#);
+        json.append(&text);
+        json.extend_from_slice(br#""}"#);
+        self.verify(
+            env,
+            self.key(player1),
+            "placement:ready",
+            &json,
+            placement.timestamp,
+            &placement.signature,
+        );
+        Ok(board_hash)
+    }
+
+    fn board_mut(&mut self, player1: bool) -> &mut Option<BytesN<32>> {
+        if player1 {
+            &mut self.player1_board
+        } else {
+            &mut self.player2_board
+        }
+    }
+}
+
+/// `board_hash` as signed, and the 32-byte field it spells.
+fn parse_board_hash(env: &Env, board_hash: &String) -> Option<(Bytes, BytesN<32>)> {
+    let len = board_hash.len();
+    if len > MAX_BOARD_HASH_LEN {
+        return None;
+    }
+    let mut buf = [0u8; MAX_BOARD_HASH_LEN as usize];
+    let text = &mut buf[..len as usize];
+    board_hash.copy_into_slice(text);
+    let digits = text.strip_prefix(b"0x").unwrap_or(text);
+    if digits.is_empty() || digits.len() > 64 {
+        return None;
+    }
+    let mut field = [0u8; 32];
+    // Right-align the digits, two per byte.
+    let offset = 64 - digits.len();
+    for (i, &digit) in digits.iter().enumerate() {
+        let nibble = (digit as char).to_digit(16)? as u8;
+        let pos = offset + i;
+        field[pos / 2] |= if pos % 2 == 0 { nibble << 4 } else { nibble };
+    }
+    Some((
+        Bytes::from_slice(env, text),
+        BytesN::from_array(env, &field),
+    ))
+}
+
+/// `shot_proof`'s public inputs as `(board_hash, row, col, is_hit)`, if
+/// row and col fit a `u32` and is_hit is a bit.
+fn parse_shot_inputs(pub_inputs: &Bytes) -> Option<(BytesN<32>, u32, u32, bool)> {
+    if pub_inputs.len() != SHOT_PUBLIC_INPUTS_LEN {
+        return None;
+    }
+    let field =
+        |i: u32| -> BytesN<32> { pub_inputs.slice(i * 32..(i + 1) * 32).try_into().unwrap() };
+    let small = |i: u32| -> Option<u32> {
+        let bytes = field(i).to_array();
+        let (high, low) = bytes.split_at(28);
+        high.iter()
+            .all(|&b| b == 0)
+            .then(|| u32::from_be_bytes(low.try_into().unwrap()))
+    };
+    let is_hit = match small(3)? {
+        0 => false,
+        1 => true,
+        _ => return None,
+    };
+    Some((field(0), small(1)?, small(2)?, is_hit))
+}
+
+fn push_hex(out: &mut Bytes, byte: u8) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    out.push_back(DIGITS[(byte >> 4) as usize]);
+    out.push_back(DIGITS[(byte & 0xf) as usize]);
+}
+
+fn push_decimal(out: &mut Bytes, mut value: u64) {
+    let mut digits = [0u8; 20];
+    let mut start = digits.len();
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(&digits[start..]);
+}
+
+/// `match_id` as bytes, if it is printable ASCII that `JSON.stringify`
+/// leaves unescaped.
+fn match_id_bytes(env: &Env, match_id: &String) -> Option<Bytes> {
+    let len = match_id.len();
+    if len == 0 || len > MAX_MATCH_ID_LEN {
+        return None;
+    }
+    let mut buf = [0u8; MAX_MATCH_ID_LEN as usize];
+    let buf = &mut buf[..len as usize];
+    match_id.copy_into_slice(buf);
+    buf.iter()
+        .all(|&b| (0x20..0x7f).contains(&b) && b != b'"' && b != b'\\')
+        .then(|| Bytes::from_slice(env, buf))
+}
+
+// ─── Match outcome ───
+
+/// Ends an open proof-mode match on the Game Hub, the way `close_match`
+/// does once the turns proof checks out.
+pub(crate) fn end_match(env: &Env, session_id: u32, player1_won: bool) -> Result<(), Error> {
+    let mut match_state = open_match_state(env, session_id)?;
+    match_state.closed = true;
+    env.storage().temporary().set(&session_id, &match_state);
+    env.storage().temporary().remove(&challenge_key(session_id));
+
+    let game_hub_addr: Address = env.storage().instance().get(&GAME_HUB).unwrap();
+    GameHubClient::new(env, &game_hub_addr).end_game(&session_id, &player1_won);
+
+    log!(
+        env,
+        "Match decided by dispute: session_id={}, player1_won={}",
+        session_id,
+        player1_won
+    );
+    Ok(())
+}
+
+pub(crate) fn open_match_state(env: &Env, session_id: u32) -> Result<MatchState, Error> {
+    let match_state: MatchState = env
+        .storage()
+        .temporary()
+        .get(&session_id)
+        .ok_or(Error::MatchNotFound)?;
+    if match_state.closed {
+        return Err(Error::MatchAlreadyClosed);
+    }
+    Ok(match_state)
+}
+
+#[contractimpl]
+impl BattleshipContract {
+    /// Bind a match opened with `open_match` to the backend's `match_id` and
+    /// the keys its players sign actions with, so their signed messages can
+    /// be disputed. Admin-only, once per session.
+    pub fn bind_match(
+        env: Env,
+        session_id: u32,
+        match_id: String,
+        player1_key: BytesN<32>,
+        player2_key: BytesN<32>,
+    ) -> Result<(), Error> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&ADMIN)
+            .ok_or(Error::NotAdmin)?;
+        admin.require_auth();
+        open_match_state(&env, session_id)?;
+
+        let key = binding_key(session_id);
+        if env.storage().temporary().has(&key) {
+            return Err(Error::MatchAlreadyBound);
+        }
+        let binding = MatchBinding {
+            match_id: match_id_bytes(&env, &match_id).ok_or(Error::InvalidMatchId)?,
+            player1_key,
+            player2_key,
+            player1_board: None,
+            player2_board: None,
+        };
+        env.storage().temporary().set(&key, &binding);
+        env.storage()
+            .temporary()
+            .extend_ttl(&key, MATCH_TTL, MATCH_TTL);
+        Ok(())
+    }
+
+    /// End the match against the player who signed both `first` and
+    /// `second`: two answers for the same cell that disagree.
+    pub fn dispute_contradiction(
+        env: Env,
+        session_id: u32,
+        accused_is_player1: bool,
+        first: SignedShotResult,
+        second: SignedShotResult,
+    ) -> Result<(), Error> {
+        open_match_state(&env, session_id)?;
+        let binding = load_binding(&env, session_id)?;
+        if (first.row, first.col) != (second.row, second.col) || first.hit == second.hit {
+            return Err(Error::NotAContradiction);
+        }
+        binding.verify_shot_result(&env, accused_is_player1, &first);
+        binding.verify_shot_result(&env, accused_is_player1, &second);
+        end_match(&env, session_id, !accused_is_player1)
+    }
+
+    /// Challenge the defender to answer `attack` on-chain. Whoever holds the
+    /// signed messages can submit them; one challenge per match is open at
+    /// a time.
+    ///
+    /// `attack` must be the one owed an answer: the attack after
+    /// `defender_attack`, the defender's own last attack, or with `None`
+    /// player 1's opening attack. Each defender attack backs one challenge,
+    /// and later challenges against the same defender must name a later
+    /// one, so an answered attack can't be challenged again.
+    pub fn challenge_unanswered(
+        env: Env,
+        session_id: u32,
+        attacker_is_player1: bool,
+        defender_attack: Option<SignedAttack>,
+        attack: SignedAttack,
+    ) -> Result<Challenge, Error> {
+        open_match_state(&env, session_id)?;
+        let binding = load_binding(&env, session_id)?;
+        let key = challenge_key(session_id);
+        if env.storage().temporary().has(&key) {
+            return Err(Error::ChallengePending);
+        }
+        let turn = turn_key(session_id, !attacker_is_player1);
+        let earliest: Option<u64> = env.storage().temporary().get(&turn);
+        let next = match defender_attack {
+            None if attacker_is_player1 && earliest.is_none() => 0,
+            None => return Err(Error::AttackNotOwed),
+            Some(previous) => {
+                if earliest.is_some_and(|earliest| previous.timestamp < earliest) {
+                    return Err(Error::AttackNotOwed);
+                }
+                binding.verify_attack(&env, !attacker_is_player1, &previous);
+                previous.timestamp.saturating_add(1)
+            }
+        };
+        binding.verify_attack(&env, attacker_is_player1, &attack);
+        env.storage().temporary().set(&turn, &next);
+        env.storage()
+            .temporary()
+            .extend_ttl(&turn, MATCH_TTL, MATCH_TTL);
+
+        let challenge = Challenge {
+            attacker_is_player1,
+            row: attack.row,
+            col: attack.col,
+            deadline: env.ledger().sequence() + ANSWER_TIMEOUT_LEDGERS,
+        };
+        env.storage().temporary().set(&key, &challenge);
+        env.storage()
+            .temporary()
+            .extend_ttl(&key, MATCH_TTL, MATCH_TTL);
+        Ok(challenge)
+    }
+
+    /// Settle the open challenge with the defender's signed answer for the
+    /// challenged cell.
+    pub fn answer_challenge(
+        env: Env,
+        session_id: u32,
+        answer: SignedShotResult,
+    ) -> Result<(), Error> {
+        open_match_state(&env, session_id)?;
+        let binding = load_binding(&env, session_id)?;
+        let key = challenge_key(session_id);
+        let challenge: Challenge = env
+            .storage()
+            .temporary()
+            .get(&key)
+            .ok_or(Error::NoChallenge)?;
+        if (answer.row, answer.col) != (challenge.row, challenge.col) {
+            return Err(Error::CellMismatch);
+        }
+        binding.verify_shot_result(&env, !challenge.attacker_is_player1, &answer);
+        env.storage().temporary().remove(&key);
+        Ok(())
+    }
+
+    /// End the match in the attacker's favour once a challenge has gone
+    /// unanswered past its deadline.
+    pub fn claim_unanswered(env: Env, session_id: u32) -> Result<(), Error> {
+        let challenge: Challenge = env
+            .storage()
+            .temporary()
+            .get(&challenge_key(session_id))
+            .ok_or(Error::NoChallenge)?;
+        if env.ledger().sequence() <= challenge.deadline {
+            return Err(Error::DeadlineNotReached);
+        }
+        end_match(&env, session_id, challenge.attacker_is_player1)
+    }
+
+    /// Record the board hash `player_is_player1`'s signed `placement:ready`
+    /// commits to, which their shot proofs are then disputed against. A
+    /// second placement with another hash ends the match against its signer.
+    pub fn register_board_hash(
+        env: Env,
+        session_id: u32,
+        player_is_player1: bool,
+        placement: SignedPlacement,
+    ) -> Result<(), Error> {
+        open_match_state(&env, session_id)?;
+        let mut binding = load_binding(&env, session_id)?;
+        let board_hash = binding.verify_placement(&env, player_is_player1, &placement)?;
+        match binding.board_mut(player_is_player1) {
+            Some(registered) if *registered == board_hash => Ok(()),
+            Some(_) => end_match(&env, session_id, !player_is_player1),
+            board => {
+                *board = Some(board_hash);
+                env.storage()
+                    .temporary()
+                    .set(&binding_key(session_id), &binding);
+                Ok(())
+            }
+        }
+    }
+
+    /// Adjudicate the defender's signed answer `claimed_result` for (`row`,
+    /// `col`) with a `shot_proof` for the same cell of the board the
+    /// defender registered: if the proof shows the other answer, the
+    /// defender lied and the match ends in the challenger's favour. A proof
+    /// that doesn't verify, or that agrees with the answer, leaves the match
+    /// as it is.
+    #[allow(clippy::too_many_arguments)]
+    pub fn dispute_shot(
+        env: Env,
+        session_id: u32,
+        defender_is_player1: bool,
+        row: u32,
+        col: u32,
+        claimed_result: SignedShotResult,
+        shot_proof: Bytes,
+        pub_inputs: Bytes,
+    ) -> Result<(), Error> {
+        open_match_state(&env, session_id)?;
+        let binding = load_binding(&env, session_id)?;
+        let (board_hash, proof_row, proof_col, proof_hit) =
+            parse_shot_inputs(&pub_inputs).ok_or(Error::InvalidPublicInputs)?;
+        if (proof_row, proof_col) != (row, col)
+            || (claimed_result.row, claimed_result.col) != (row, col)
+        {
+            return Err(Error::CellMismatch);
+        }
+        let registered = if defender_is_player1 {
+            &binding.player1_board
+        } else {
+            &binding.player2_board
+        };
+        match registered {
+            Some(registered) if *registered == board_hash => {}
+            Some(_) => return Err(Error::BoardHashMismatch),
+            None => return Err(Error::BoardNotRegistered),
+        }
+        binding.verify_shot_result(&env, defender_is_player1, &claimed_result);
+        if proof_hit == claimed_result.hit {
+            return Err(Error::NotAContradiction);
+        }
+
+        let verifier_addr: Address = env.storage().instance().get(&VERIFIER).unwrap();
+        let verifier = VerifierClient::new(&env, &verifier_addr);
+        if verifier.try_verify_shot(&shot_proof, &pub_inputs) != Ok(Ok(true)) {
+            return Err(Error::VerificationFailed);
+        }
+        end_match(&env, session_id, !defender_is_player1)
+    }
+
+    pub fn get_binding(env: Env, session_id: u32) -> Result<MatchBinding, Error> {
+        load_binding(&env, session_id)
+    }
+
+    pub fn get_challenge(env: Env, session_id: u32) -> Option<Challenge> {
+        env.storage().temporary().get(&challenge_key(session_id))
+    }
+}
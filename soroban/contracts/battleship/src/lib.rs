@@ -5,6 +5,9 @@ use soroban_sdk::{
     Address, Bytes, Env, Symbol,
 };
 
+pub mod commit_reveal;
+pub mod disputes;
+
 // ─── Cross-contract: Verifier ───
 
 #[contracterror]
@@ -20,6 +23,7 @@ pub enum VerifierError {
 pub trait Verifier {
     fn verify_board(env: Env, proof: Bytes, public_inputs: Bytes) -> Result<bool, VerifierError>;
     fn verify_turns(env: Env, proof: Bytes, public_inputs: Bytes) -> Result<bool, VerifierError>;
+    fn verify_shot(env: Env, proof: Bytes, public_inputs: Bytes) -> Result<bool, VerifierError>;
 }
 
 // ─── Cross-contract: Game Hub ───
@@ -64,6 +68,31 @@ pub enum Error {
     VerificationFailed = 2,
     MatchNotFound = 3,
     MatchAlreadyClosed = 4,
+    NotAPlayer = 5,
+    WrongPhase = 6,
+    NotYourTurn = 7,
+    ShotPending = 8,
+    NoPendingShot = 9,
+    CellOutOfBounds = 10,
+    CellAlreadyShot = 11,
+    AlreadyCommitted = 12,
+    AlreadyRevealed = 13,
+    DeadlineNotReached = 14,
+    NothingToClaim = 15,
+    InvalidStake = 16,
+    UnsupportedGrid = 17,
+    MatchNotBound = 18,
+    MatchAlreadyBound = 19,
+    InvalidMatchId = 20,
+    NotAContradiction = 21,
+    ChallengePending = 22,
+    NoChallenge = 23,
+    CellMismatch = 24,
+    InvalidBoardHash = 25,
+    BoardNotRegistered = 26,
+    InvalidPublicInputs = 27,
+    BoardHashMismatch = 28,
+    AttackNotOwed = 29,
 }
 
 // ─── Contract ───
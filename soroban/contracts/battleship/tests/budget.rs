@@ -0,0 +1,183 @@
+//! Budget of every `BattleshipContract` entry point, checked against
+//! `budget_baseline.json` (see `budget-harness`). The verifier and Game Hub
+//! are the `common` stubs, so the numbers cover this contract's own work
+//! plus the hub's one write per ended match; proof verification is measured
+//! in the verifier's budget test.
+
+mod common;
+
+use battleship::commit_reveal::{board_commitment, Ship, MOVE_TIMEOUT_LEDGERS};
+use battleship::disputes::ANSWER_TIMEOUT_LEDGERS;
+use battleship::{BattleshipContract, BattleshipContractClient};
+use budget_harness::Harness;
+use common::{advance, attack, placement, public_key, setup, shot_result, MATCH_ID};
+use soroban_sdk::{Bytes, BytesN};
+
+/// bb v0.87.0 proof size, and `board_validity`'s 22 public inputs.
+const PROOF_LEN: usize = 14_592;
+const PUBLIC_INPUTS_LEN: usize = 22 * 32;
+
+const BASELINE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/budget_baseline.json");
+
+#[test]
+fn entry_point_budgets() -> Result<(), String> {
+    let s = setup();
+    let env = &s.env;
+    let (p1, p2) = (&s.p1, &s.p2);
+    let proof = Bytes::from_slice(env, &[0u8; PROOF_LEN]);
+    let public_inputs = Bytes::from_slice(env, &[0u8; PUBLIC_INPUTS_LEN]);
+
+    let mut harness = Harness::new(env, BASELINE, "native");
+    let id = harness.measure("__constructor", || {
+        env.register(BattleshipContract, (&s.admin, &s.verifier, &s.hub.address))
+    });
+    let client = BattleshipContractClient::new(env, &id);
+
+    let verified = harness.measure("verify_board", || {
+        client.verify_board(&proof, &public_inputs)
+    });
+    assert!(verified);
+    let session = harness.measure("open_match", || client.open_match(p1, p2));
+    harness.measure("get_match", || client.get_match(&session));
+    harness.measure("get_session_counter", || client.get_session_counter());
+    harness.measure("close_match", || {
+        client.close_match(&session, &proof, &public_inputs, &true)
+    });
+    assert!(client.get_match(&session).closed);
+
+    // A full-size commit-reveal match: the 10x10 fleet, with player 1 sinking
+    // it shot for shot while player 2 misses.
+    let ship = |row, size| Ship {
+        row,
+        col: 0,
+        size,
+        horizontal: true,
+    };
+    let ships = soroban_sdk::vec![
+        env,
+        ship(0, 5),
+        ship(2, 4),
+        ship(4, 3),
+        ship(6, 3),
+        ship(8, 2)
+    ];
+    let salt = BytesN::from_array(env, &[7u8; 32]);
+    let commitment = board_commitment(env, &ships, &salt);
+    let session = harness.measure("open_commit_match", || {
+        client.open_commit_match(p1, p2, &s.token.address, &1, &10)
+    });
+    harness.measure("commit_board", || {
+        client.commit_board(&session, p1, &commitment)
+    });
+    client.commit_board(&session, p2, &commitment);
+    let fleet_cells = ships
+        .iter()
+        .flat_map(|ship| (0..ship.size).map(move |col| (ship.row, col)));
+    for (i, (row, col)) in fleet_cells.enumerate() {
+        if i == 0 {
+            harness.measure("fire", || client.fire(&session, p1, &row, &col));
+            harness.measure("answer_shot", || client.answer_shot(&session, p2, &true));
+        } else {
+            client.fire(&session, p1, &row, &col);
+            client.answer_shot(&session, p2, &true);
+        }
+        if i < 16 {
+            // Odd rows are empty.
+            client.fire(&session, p2, &(1 + 2 * (i as u32 / 10)), &(i as u32 % 10));
+            client.answer_shot(&session, p1, &false);
+        }
+    }
+    harness.measure("get_commit_match", || client.get_commit_match(&session));
+    client.reveal_board(&session, p1, &ships, &salt);
+    let honest = harness.measure("reveal_board", || {
+        client.reveal_board(&session, p2, &ships, &salt)
+    });
+    assert!(honest);
+
+    // A defender who stops answering, with both stakes paid to the attacker.
+    let session = client.open_commit_match(p1, p2, &s.token.address, &1, &10);
+    client.commit_board(&session, p1, &commitment);
+    client.commit_board(&session, p2, &commitment);
+    client.fire(&session, p1, &0, &0);
+    advance(env, MOVE_TIMEOUT_LEDGERS + 1);
+    harness.measure("claim_timeout", || client.claim_timeout(&session, p1));
+    assert_eq!(client.get_commit_match(&session).winner, Some(p1.clone()));
+
+    // Disputes over a 16-character backend match id: a challenged attack
+    // answered in time, then a contradiction that ends the match.
+    let session = client.open_match(p1, p2);
+    harness.measure("bind_match", || {
+        client.bind_match(
+            &session,
+            &soroban_sdk::String::from_str(env, MATCH_ID),
+            &public_key(env, &s.player1),
+            &public_key(env, &s.player2),
+        )
+    });
+    let result = |hit: bool| shot_result(env, &s.player2, 3, 4, hit, 0);
+    // Player 2's attack, owed an answer after player 1's opening one.
+    harness.measure("challenge_unanswered", || {
+        client.challenge_unanswered(
+            &session,
+            &false,
+            &Some(attack(env, &s.player1, 3, 4, 0)),
+            &attack(env, &s.player2, 5, 6, 0),
+        )
+    });
+    harness.measure("get_challenge", || client.get_challenge(&session));
+    harness.measure("answer_challenge", || {
+        client.answer_challenge(&session, &shot_result(env, &s.player1, 5, 6, true, 0))
+    });
+    harness.measure("get_binding", || client.get_binding(&session));
+    harness.measure("dispute_contradiction", || {
+        client.dispute_contradiction(&session, &false, &result(true), &result(false))
+    });
+    assert!(client.get_match(&session).closed);
+
+    // A challenge left unanswered past its deadline.
+    let session = client.open_match(p1, p2);
+    client.bind_match(
+        &session,
+        &soroban_sdk::String::from_str(env, MATCH_ID),
+        &public_key(env, &s.player1),
+        &public_key(env, &s.player2),
+    );
+    client.challenge_unanswered(&session, &true, &None, &attack(env, &s.player1, 3, 4, 0));
+    advance(env, ANSWER_TIMEOUT_LEDGERS + 1);
+    harness.measure("claim_unanswered", || client.claim_unanswered(&session));
+    assert!(client.get_match(&session).closed);
+
+    // A shot proof showing a hit where player 2 signed a miss.
+    let session = client.open_match(p1, p2);
+    client.bind_match(
+        &session,
+        &soroban_sdk::String::from_str(env, MATCH_ID),
+        &public_key(env, &s.player1),
+        &public_key(env, &s.player2),
+    );
+    let board_hash = format!("0x{}", "5a".repeat(32));
+    let placement = placement(env, &s.player2, &board_hash, 0);
+    harness.measure("register_board_hash", || {
+        client.register_board_hash(&session, &false, &placement)
+    });
+    let mut shot_inputs = [0u8; 128];
+    shot_inputs[..32].fill(0x5a);
+    shot_inputs[63] = 3;
+    shot_inputs[95] = 4;
+    shot_inputs[127] = 1;
+    let shot_inputs = Bytes::from_slice(env, &shot_inputs);
+    harness.measure("dispute_shot", || {
+        client.dispute_shot(
+            &session,
+            &false,
+            &3,
+            &4,
+            &result(false),
+            &proof,
+            &shot_inputs,
+        )
+    });
+    assert!(client.get_match(&session).closed);
+
+    harness.finish()
+}
@@ -0,0 +1,348 @@
+//! Commit-reveal matches end to end on a 6x6 grid (fleet `[2, 2, 3]`), with
+//! a stub Game Hub that records how each session ended.
+
+mod common;
+
+use battleship::commit_reveal::{
+    board_commitment, CommitPhase, Ship, MOVE_TIMEOUT_LEDGERS, REVEAL_TIMEOUT_LEDGERS,
+};
+use battleship::Error;
+use common::{advance, setup, Setup, STAKE};
+use soroban_sdk::{testutils::Address as _, Address, BytesN, Env, Vec};
+
+const GRID: u32 = 6;
+
+fn ship(row: u32, col: u32, size: u32, horizontal: bool) -> Ship {
+    Ship {
+        row,
+        col,
+        size,
+        horizontal,
+    }
+}
+
+/// Ships on rows 0, 2 and 4 from column 0: cells (0,0-1), (2,0-1), (4,0-2).
+fn fleet(env: &Env) -> Vec<Ship> {
+    soroban_sdk::vec![
+        env,
+        ship(0, 0, 2, true),
+        ship(2, 0, 2, true),
+        ship(4, 0, 3, true)
+    ]
+}
+
+const FLEET_CELLS: [(u32, u32); 7] = [(0, 0), (0, 1), (2, 0), (2, 1), (4, 0), (4, 1), (4, 2)];
+
+fn salt(env: &Env, byte: u8) -> BytesN<32> {
+    BytesN::from_array(env, &[byte; 32])
+}
+
+/// Opens a match and commits `fleet` for both players, player 2 with
+/// `p2_ships` instead when given.
+fn open(s: &Setup, p2_ships: Option<&Vec<Ship>>) -> u32 {
+    let session = s
+        .client
+        .open_commit_match(&s.p1, &s.p2, &s.token.address, &STAKE, &GRID);
+    let p2_ships = p2_ships.cloned().unwrap_or_else(|| fleet(&s.env));
+    s.client.commit_board(
+        &session,
+        &s.p1,
+        &board_commitment(&s.env, &fleet(&s.env), &salt(&s.env, 1)),
+    );
+    s.client.commit_board(
+        &session,
+        &s.p2,
+        &board_commitment(&s.env, &p2_ships, &salt(&s.env, 2)),
+    );
+    session
+}
+
+fn shoot(
+    s: &Setup,
+    session: u32,
+    attacker: &Address,
+    defender: &Address,
+    cell: (u32, u32),
+    hit: bool,
+) {
+    s.client.fire(&session, attacker, &cell.0, &cell.1);
+    s.client.answer_shot(&session, defender, &hit);
+}
+
+/// Player 1 sinks player 2's fleet while player 2 misses along row 5; player
+/// 2 answers `p2_answers` for the cells player 1 fires at.
+fn play(s: &Setup, session: u32, p1_targets: &[(u32, u32)], p2_answers: &[bool]) {
+    for (i, (&target, &hit)) in p1_targets.iter().zip(p2_answers).enumerate() {
+        shoot(s, session, &s.p1, &s.p2, target, hit);
+        if s.client.get_commit_match(&session).phase == CommitPhase::Playing {
+            shoot(s, session, &s.p2, &s.p1, (5, i as u32 % GRID), false);
+        }
+    }
+}
+
+#[test]
+fn honest_match_returns_stakes_and_reports_winner() {
+    let s = setup();
+    let session = open(&s, None);
+    assert_eq!(s.token.balance(&s.client.address), 2 * STAKE);
+
+    play(&s, session, &FLEET_CELLS, &[true; 7]);
+    let state = s.client.get_commit_match(&session);
+    assert_eq!(state.phase, CommitPhase::Revealing);
+    assert_eq!(state.winner, Some(s.p1.clone()));
+
+    assert!(s
+        .client
+        .reveal_board(&session, &s.p1, &fleet(&s.env), &salt(&s.env, 1)));
+    assert_eq!(s.hub.result(&session), None);
+    assert!(s
+        .client
+        .reveal_board(&session, &s.p2, &fleet(&s.env), &salt(&s.env, 2)));
+
+    assert_eq!(
+        s.client.get_commit_match(&session).phase,
+        CommitPhase::Closed
+    );
+    assert_eq!(s.hub.result(&session), Some(true));
+    assert_eq!(s.token.balance(&s.p1), STAKE);
+    assert_eq!(s.token.balance(&s.p2), STAKE);
+    assert_eq!(s.token.balance(&s.client.address), 0);
+}
+
+#[test]
+fn reveal_contradicting_an_answer_forfeits_the_stake() {
+    let s = setup();
+    let session = open(&s, None);
+    // Player 2 claims a hit on empty water at (5, 5), then answers the real
+    // ship cells until seven "hits" end the game.
+    let mut targets = std::vec![(5, 5)];
+    targets.extend_from_slice(&FLEET_CELLS[..6]);
+    play(&s, session, &targets, &[true; 7]);
+    assert_eq!(
+        s.client.get_commit_match(&session).phase,
+        CommitPhase::Revealing
+    );
+
+    assert!(!s
+        .client
+        .reveal_board(&session, &s.p2, &fleet(&s.env), &salt(&s.env, 2)));
+    assert_eq!(
+        s.client.get_commit_match(&session).phase,
+        CommitPhase::Closed
+    );
+    assert_eq!(s.hub.result(&session), Some(true));
+    assert_eq!(s.token.balance(&s.p1), 2 * STAKE);
+    assert_eq!(s.token.balance(&s.p2), 0);
+}
+
+type Board = fn(&Env) -> Vec<Ship>;
+
+#[test]
+fn invalid_boards_forfeit_at_reveal() {
+    let overlapping = |env: &Env| {
+        soroban_sdk::vec![
+            env,
+            ship(0, 0, 2, true),
+            ship(0, 1, 2, false),
+            ship(4, 0, 3, true)
+        ]
+    };
+    let off_grid = |env: &Env| {
+        soroban_sdk::vec![
+            env,
+            ship(0, 0, 2, true),
+            ship(2, 0, 2, true),
+            ship(4, 4, 3, true)
+        ]
+    };
+    let wrong_sizes = |env: &Env| {
+        soroban_sdk::vec![
+            env,
+            ship(0, 0, 3, true),
+            ship(2, 0, 2, true),
+            ship(4, 0, 2, true)
+        ]
+    };
+    let missing_ship = |env: &Env| soroban_sdk::vec![env, ship(0, 0, 2, true), ship(2, 0, 2, true)];
+    let cases: [(&str, Board); 4] = [
+        ("overlapping", overlapping),
+        ("off grid", off_grid),
+        ("wrong sizes", wrong_sizes),
+        ("missing ship", missing_ship),
+    ];
+    for (name, board) in cases {
+        let s = setup();
+        let ships = board(&s.env);
+        let session = open(&s, Some(&ships));
+        // Player 2 answers as if it had the standard fleet, so its answers
+        // match the ships wherever the revealed board has them.
+        play(&s, session, &FLEET_CELLS, &[true; 7]);
+        assert!(
+            !s.client
+                .reveal_board(&session, &s.p2, &ships, &salt(&s.env, 2)),
+            "{name}"
+        );
+        assert_eq!(s.token.balance(&s.p1), 2 * STAKE, "{name}");
+    }
+}
+
+#[test]
+fn reveal_must_open_the_commitment() {
+    let s = setup();
+    let session = open(&s, None);
+    play(&s, session, &FLEET_CELLS, &[true; 7]);
+    assert!(s
+        .client
+        .reveal_board(&session, &s.p1, &fleet(&s.env), &salt(&s.env, 1)));
+    assert!(!s
+        .client
+        .reveal_board(&session, &s.p2, &fleet(&s.env), &salt(&s.env, 9)));
+    assert_eq!(s.hub.result(&session), Some(true));
+    assert_eq!(s.token.balance(&s.p1), 2 * STAKE);
+}
+
+#[test]
+fn moves_follow_the_turn_order() {
+    let s = setup();
+    let outsider = Address::generate(&s.env);
+    let session = s
+        .client
+        .open_commit_match(&s.p1, &s.p2, &s.token.address, &STAKE, &GRID);
+    assert_eq!(
+        s.client.try_fire(&session, &s.p1, &0, &0),
+        Err(Ok(Error::WrongPhase))
+    );
+    let commitment = board_commitment(&s.env, &fleet(&s.env), &salt(&s.env, 1));
+    assert_eq!(
+        s.client.try_commit_board(&session, &outsider, &commitment),
+        Err(Ok(Error::NotAPlayer))
+    );
+    s.client.commit_board(&session, &s.p1, &commitment);
+    assert_eq!(
+        s.client.try_commit_board(&session, &s.p1, &commitment),
+        Err(Ok(Error::AlreadyCommitted))
+    );
+    s.client.commit_board(&session, &s.p2, &commitment);
+
+    assert_eq!(
+        s.client.try_fire(&session, &s.p2, &0, &0),
+        Err(Ok(Error::NotYourTurn))
+    );
+    assert_eq!(
+        s.client.try_fire(&session, &s.p1, &6, &0),
+        Err(Ok(Error::CellOutOfBounds))
+    );
+    assert_eq!(
+        s.client.try_answer_shot(&session, &s.p2, &false),
+        Err(Ok(Error::NoPendingShot))
+    );
+    s.client.fire(&session, &s.p1, &3, &3);
+    assert_eq!(
+        s.client.try_fire(&session, &s.p1, &3, &4),
+        Err(Ok(Error::ShotPending))
+    );
+    assert_eq!(
+        s.client.try_answer_shot(&session, &s.p1, &false),
+        Err(Ok(Error::NotYourTurn))
+    );
+    s.client.answer_shot(&session, &s.p2, &false);
+
+    shoot(&s, session, &s.p2, &s.p1, (3, 3), false);
+    assert_eq!(
+        s.client.try_fire(&session, &s.p1, &3, &3),
+        Err(Ok(Error::CellAlreadyShot))
+    );
+    assert_eq!(
+        s.client
+            .try_reveal_board(&session, &s.p1, &fleet(&s.env), &salt(&s.env, 1)),
+        Err(Ok(Error::WrongPhase))
+    );
+}
+
+#[test]
+fn open_rejects_bad_parameters() {
+    let s = setup();
+    assert_eq!(
+        s.client
+            .try_open_commit_match(&s.p1, &s.p2, &s.token.address, &-1, &GRID),
+        Err(Ok(Error::InvalidStake))
+    );
+    assert_eq!(
+        s.client
+            .try_open_commit_match(&s.p1, &s.p2, &s.token.address, &STAKE, &8),
+        Err(Ok(Error::UnsupportedGrid))
+    );
+    assert_eq!(
+        s.client
+            .try_open_commit_match(&s.p1, &s.p1, &s.token.address, &STAKE, &GRID),
+        Err(Ok(Error::NotAPlayer))
+    );
+}
+
+#[test]
+fn stalled_defender_forfeits_after_the_move_timeout() {
+    let s = setup();
+    let session = open(&s, None);
+    s.client.fire(&session, &s.p1, &0, &0);
+    assert_eq!(
+        s.client.try_claim_timeout(&session, &s.p1),
+        Err(Ok(Error::DeadlineNotReached))
+    );
+    advance(&s.env, MOVE_TIMEOUT_LEDGERS + 1);
+    // The stalling player can't claim their own timeout.
+    assert_eq!(
+        s.client.try_claim_timeout(&session, &s.p2),
+        Err(Ok(Error::NothingToClaim))
+    );
+    s.client.claim_timeout(&session, &s.p1);
+
+    assert_eq!(s.hub.result(&session), Some(true));
+    assert_eq!(s.token.balance(&s.p1), 2 * STAKE);
+    assert_eq!(s.token.balance(&s.p2), 0);
+    assert_eq!(
+        s.client.try_claim_timeout(&session, &s.p1),
+        Err(Ok(Error::MatchAlreadyClosed))
+    );
+}
+
+#[test]
+fn missing_commitment_refunds_the_committed_player() {
+    let s = setup();
+    let session = s
+        .client
+        .open_commit_match(&s.p1, &s.p2, &s.token.address, &STAKE, &GRID);
+    s.client.commit_board(
+        &session,
+        &s.p2,
+        &board_commitment(&s.env, &fleet(&s.env), &salt(&s.env, 2)),
+    );
+    advance(&s.env, REVEAL_TIMEOUT_LEDGERS + 1);
+    s.client.claim_timeout(&session, &s.p2);
+
+    assert_eq!(s.hub.result(&session), Some(false));
+    assert_eq!(s.token.balance(&s.p1), STAKE);
+    assert_eq!(s.token.balance(&s.p2), STAKE);
+}
+
+#[test]
+fn missing_reveal_forfeits_and_no_reveals_refund() {
+    let s = setup();
+    let session = open(&s, None);
+    play(&s, session, &FLEET_CELLS, &[true; 7]);
+    s.client
+        .reveal_board(&session, &s.p2, &fleet(&s.env), &salt(&s.env, 2));
+    advance(&s.env, REVEAL_TIMEOUT_LEDGERS + 1);
+    // Player 1 won the game but never showed an honest board.
+    s.client.claim_timeout(&session, &s.p2);
+    assert_eq!(s.hub.result(&session), Some(false));
+    assert_eq!(s.token.balance(&s.p2), 2 * STAKE);
+
+    let s = setup();
+    let session = open(&s, None);
+    play(&s, session, &FLEET_CELLS, &[true; 7]);
+    advance(&s.env, REVEAL_TIMEOUT_LEDGERS + 1);
+    s.client.claim_timeout(&session, &s.p2);
+    assert_eq!(s.hub.result(&session), Some(true));
+    assert_eq!(s.token.balance(&s.p1), STAKE);
+    assert_eq!(s.token.balance(&s.p2), STAKE);
+}
@@ -0,0 +1,200 @@
+//! Contracts and helpers shared by the battleship tests: a stub verifier, a
+//! Game Hub that records how each session ended, and the backend's action
+//! signing.
+#![allow(dead_code)]
+
+use battleship::disputes::{SignedAttack, SignedPlacement, SignedShotResult};
+use battleship::{BattleshipContract, BattleshipContractClient};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    token::{StellarAssetClient, TokenClient},
+    Address, BytesN, Env, String,
+};
+
+/// Minted to each player by `setup`.
+pub const STAKE: i128 = 1_000;
+/// The backend match id the `sign_*` helpers sign for.
+pub const MATCH_ID: &str = "9f3c2a7b1e04d5c6";
+
+pub mod stub_verifier {
+    use soroban_sdk::{contract, contractimpl, Bytes, Env};
+
+    #[contract]
+    pub struct StubVerifier;
+
+    /// Accepts every board and turns proof, and any non-empty shot proof.
+    #[contractimpl]
+    impl StubVerifier {
+        pub fn verify_board(_env: Env, _proof: Bytes, _public_inputs: Bytes) -> bool {
+            true
+        }
+
+        pub fn verify_turns(_env: Env, _proof: Bytes, _public_inputs: Bytes) -> bool {
+            true
+        }
+
+        pub fn verify_shot(_env: Env, proof: Bytes, _public_inputs: Bytes) -> bool {
+            assert!(!proof.is_empty(), "invalid proof");
+            true
+        }
+    }
+}
+
+pub mod recording_game_hub {
+    use soroban_sdk::{contract, contractimpl, Address, Env};
+
+    #[contract]
+    pub struct RecordingGameHub;
+
+    #[contractimpl]
+    impl RecordingGameHub {
+        pub fn start_game(
+            _env: Env,
+            _game_id: Address,
+            _session_id: u32,
+            _player1: Address,
+            _player2: Address,
+            _player1_points: i128,
+            _player2_points: i128,
+        ) {
+        }
+
+        pub fn end_game(env: Env, session_id: u32, player1_won: bool) {
+            env.storage().instance().set(&session_id, &player1_won);
+        }
+
+        pub fn result(env: Env, session_id: u32) -> Option<bool> {
+            env.storage().instance().get(&session_id)
+        }
+    }
+}
+
+use recording_game_hub::RecordingGameHubClient;
+
+pub struct Setup<'a> {
+    pub env: Env,
+    pub client: BattleshipContractClient<'a>,
+    pub hub: RecordingGameHubClient<'a>,
+    pub admin: Address,
+    pub verifier: Address,
+    /// A Stellar asset `p1` and `p2` each hold `STAKE` of.
+    pub token: TokenClient<'a>,
+    pub p1: Address,
+    pub p2: Address,
+    /// The keys player 1 and player 2 sign backend actions with.
+    pub player1: SigningKey,
+    pub player2: SigningKey,
+}
+
+/// A battleship contract on the stub verifier and the recording Game Hub,
+/// with every auth mocked.
+pub fn setup<'a>() -> Setup<'a> {
+    let env = Env::default();
+    env.mock_all_auths();
+    // Keep instances and balances live across the commit-reveal timeouts.
+    env.ledger().with_mut(|ledger| {
+        ledger.min_persistent_entry_ttl = 100_000;
+        ledger.min_temp_entry_ttl = 100_000;
+        ledger.max_entry_ttl = 1_000_000;
+    });
+    let verifier = env.register(stub_verifier::StubVerifier, ());
+    let hub = env.register(recording_game_hub::RecordingGameHub, ());
+    let admin = Address::generate(&env);
+    let id = env.register(BattleshipContract, (&admin, &verifier, &hub));
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+    let (p1, p2) = (Address::generate(&env), Address::generate(&env));
+    for player in [&p1, &p2] {
+        StellarAssetClient::new(&env, &token).mint(player, &STAKE);
+    }
+    Setup {
+        client: BattleshipContractClient::new(&env, &id),
+        hub: RecordingGameHubClient::new(&env, &hub),
+        token: TokenClient::new(&env, &token),
+        admin,
+        verifier,
+        p1,
+        p2,
+        player1: SigningKey::from_bytes(&[1u8; 32]),
+        player2: SigningKey::from_bytes(&[2u8; 32]),
+        env,
+    }
+}
+
+impl Setup<'_> {
+    /// A match opened the way the backend does it, with the server address
+    /// as both players, and bound to `MATCH_ID` and the players' keys.
+    pub fn bound_match(&self) -> u32 {
+        let server = Address::generate(&self.env);
+        let session = self.client.open_match(&server, &server);
+        self.client.bind_match(
+            &session,
+            &String::from_str(&self.env, MATCH_ID),
+            &public_key(&self.env, &self.player1),
+            &public_key(&self.env, &self.player2),
+        );
+        session
+    }
+}
+
+/// Move the ledger `ledgers` sequence numbers forward.
+pub fn advance(env: &Env, ledgers: u32) {
+    env.ledger()
+        .with_mut(|ledger| ledger.sequence_number += ledgers);
+}
+
+pub fn public_key(env: &Env, key: &SigningKey) -> BytesN<32> {
+    BytesN::from_array(env, &key.verifying_key().to_bytes())
+}
+
+/// `signAction(keys, action, data)` for `data` already JSON-encoded.
+pub fn sign(env: &Env, key: &SigningKey, action: &str, json: &str, timestamp: u64) -> BytesN<64> {
+    let public_key: std::string::String = key
+        .verifying_key()
+        .to_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    let message = format!("{public_key}:{action}:{json}:{timestamp}");
+    BytesN::from_array(env, &key.sign(message.as_bytes()).to_bytes())
+}
+
+pub fn attack(env: &Env, key: &SigningKey, row: u32, col: u32, timestamp: u64) -> SignedAttack {
+    let json = format!(r#"{{"matchId":"{MATCH_ID}","row":{row},"col":{col}}}"#);
+    SignedAttack {
+        row,
+        col,
+        timestamp,
+        signature: sign(env, key, "battle:attack", &json, timestamp),
+    }
+}
+
+pub fn shot_result(
+    env: &Env,
+    key: &SigningKey,
+    row: u32,
+    col: u32,
+    hit: bool,
+    timestamp: u64,
+) -> SignedShotResult {
+    let result = if hit { "hit" } else { "miss" };
+    let json = format!(r#"{{"matchId":"{MATCH_ID}","row":{row},"col":{col},"result":"{result}"}}"#);
+    SignedShotResult {
+        row,
+        col,
+        hit,
+        timestamp,
+        signature: sign(env, key, "battle:shot_result", &json, timestamp),
+    }
+}
+
+pub fn placement(env: &Env, key: &SigningKey, board_hash: &str, timestamp: u64) -> SignedPlacement {
+    let json = format!(r#"{{"matchId":"{MATCH_ID}","boardHash":"{board_hash}"}}"#);
+    SignedPlacement {
+        board_hash: String::from_str(env, board_hash),
+        timestamp,
+        signature: sign(env, key, "placement:ready", &json, timestamp),
+    }
+}
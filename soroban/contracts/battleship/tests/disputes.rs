@@ -0,0 +1,388 @@
+//! Disputes over signed backend messages, signed here exactly as the web
+//! client's `signAction` does. The stub verifier accepts any non-empty
+//! shot proof.
+
+mod common;
+
+use battleship::disputes::ANSWER_TIMEOUT_LEDGERS;
+use battleship::Error;
+use common::{advance, attack, placement, public_key, setup, shot_result, MATCH_ID};
+use soroban_sdk::{testutils::Address as _, Address, Bytes, BytesN, Env, String};
+
+#[test]
+fn contradicting_results_end_the_match_against_the_signer() {
+    let s = setup();
+    let session = s.bound_match();
+    let hit = shot_result(&s.env, &s.player2, 3, 4, true, 1_760_000_000_000);
+    let miss = shot_result(&s.env, &s.player2, 3, 4, false, 1_760_000_009_000);
+
+    assert_eq!(
+        s.client
+            .try_dispute_contradiction(&session, &false, &hit, &hit),
+        Err(Ok(Error::NotAContradiction))
+    );
+    let other_cell = shot_result(&s.env, &s.player2, 3, 5, false, 1_760_000_009_000);
+    assert_eq!(
+        s.client
+            .try_dispute_contradiction(&session, &false, &hit, &other_cell),
+        Err(Ok(Error::NotAContradiction))
+    );
+
+    s.client
+        .dispute_contradiction(&session, &false, &hit, &miss);
+    assert_eq!(s.hub.result(&session), Some(true));
+    assert!(s.client.get_match(&session).closed);
+    assert_eq!(
+        s.client
+            .try_dispute_contradiction(&session, &false, &hit, &miss),
+        Err(Ok(Error::MatchAlreadyClosed))
+    );
+}
+
+#[test]
+fn results_must_be_signed_by_the_accused() {
+    let s = setup();
+    let session = s.bound_match();
+    // Player 1 can't frame player 2 with results of their own signing.
+    let hit = shot_result(&s.env, &s.player1, 3, 4, true, 1_760_000_000_000);
+    let miss = shot_result(&s.env, &s.player1, 3, 4, false, 1_760_000_009_000);
+    assert!(s
+        .client
+        .try_dispute_contradiction(&session, &false, &hit, &miss)
+        .is_err());
+
+    // Nor by altering a genuine result after the fact.
+    let mut forged = shot_result(&s.env, &s.player2, 3, 4, true, 1_760_000_000_000);
+    let miss = shot_result(&s.env, &s.player2, 3, 4, false, 1_760_000_009_000);
+    forged.timestamp += 1;
+    assert!(s
+        .client
+        .try_dispute_contradiction(&session, &false, &forged, &miss)
+        .is_err());
+    assert!(!s.client.get_match(&session).closed);
+}
+
+#[test]
+fn unanswered_attack_forfeits_after_the_deadline() {
+    let s = setup();
+    let session = s.bound_match();
+    let shot = attack(&s.env, &s.player1, 0, 0, 1_760_000_000_000);
+    s.client.challenge_unanswered(&session, &true, &None, &shot);
+    assert_eq!(
+        s.client
+            .try_challenge_unanswered(&session, &true, &None, &shot),
+        Err(Ok(Error::ChallengePending))
+    );
+    assert_eq!(
+        s.client.try_claim_unanswered(&session),
+        Err(Ok(Error::DeadlineNotReached))
+    );
+
+    advance(&s.env, ANSWER_TIMEOUT_LEDGERS + 1);
+    s.client.claim_unanswered(&session);
+    assert_eq!(s.hub.result(&session), Some(true));
+    assert_eq!(s.client.get_challenge(&session), None);
+}
+
+#[test]
+fn signed_answer_settles_a_challenge() {
+    let s = setup();
+    let session = s.bound_match();
+    let opening = attack(&s.env, &s.player1, 0, 0, 1_760_000_000_000);
+    let shot = attack(&s.env, &s.player2, 5, 9, 1_760_000_000_500);
+    s.client
+        .challenge_unanswered(&session, &false, &Some(opening), &shot);
+
+    let wrong_cell = shot_result(&s.env, &s.player1, 9, 5, false, 1_760_000_001_000);
+    assert_eq!(
+        s.client.try_answer_challenge(&session, &wrong_cell),
+        Err(Ok(Error::CellMismatch))
+    );
+    // The attacker can't answer for the defender.
+    let by_attacker = shot_result(&s.env, &s.player2, 5, 9, false, 1_760_000_001_000);
+    assert!(s
+        .client
+        .try_answer_challenge(&session, &by_attacker)
+        .is_err());
+
+    let answer = shot_result(&s.env, &s.player1, 5, 9, false, 1_760_000_001_000);
+    s.client.answer_challenge(&session, &answer);
+    assert_eq!(s.client.get_challenge(&session), None);
+    advance(&s.env, ANSWER_TIMEOUT_LEDGERS + 1);
+    assert_eq!(
+        s.client.try_claim_unanswered(&session),
+        Err(Ok(Error::NoChallenge))
+    );
+    assert!(!s.client.get_match(&session).closed);
+}
+
+/// Only the attack after the defender's last attack is owed an answer, and
+/// each defender attack backs one challenge.
+#[test]
+fn challenges_follow_turn_order() {
+    let s = setup();
+    let session = s.bound_match();
+    let t = 1_760_000_000_000;
+    let p1_first = attack(&s.env, &s.player1, 0, 0, t);
+    let p2_first = attack(&s.env, &s.player2, 1, 1, t + 1_000);
+    let p1_second = attack(&s.env, &s.player1, 2, 2, t + 2_000);
+    let p2_second = attack(&s.env, &s.player2, 3, 3, t + 3_000);
+    // Player 1's attacks are on even cells, so player 2 answers those.
+    let answer = |cell: u32, ts| {
+        let defender = if cell.is_multiple_of(2) {
+            &s.player2
+        } else {
+            &s.player1
+        };
+        let result = shot_result(&s.env, defender, cell, cell, false, ts);
+        s.client.answer_challenge(&session, &result);
+    };
+
+    // Player 2 never opens, so their attacks need player 1's before them.
+    assert_eq!(
+        s.client
+            .try_challenge_unanswered(&session, &false, &None, &p2_first),
+        Err(Ok(Error::AttackNotOwed))
+    );
+    // The defender's previous attack has to be signed by the defender.
+    assert!(s
+        .client
+        .try_challenge_unanswered(&session, &false, &Some(p2_first.clone()), &p2_first)
+        .is_err());
+
+    s.client
+        .challenge_unanswered(&session, &true, &None, &p1_first);
+    answer(0, t + 500);
+    // The opening attack was challenged once already.
+    assert_eq!(
+        s.client
+            .try_challenge_unanswered(&session, &true, &None, &p1_first),
+        Err(Ok(Error::AttackNotOwed))
+    );
+
+    s.client
+        .challenge_unanswered(&session, &false, &Some(p1_first.clone()), &p2_first);
+    answer(1, t + 1_500);
+    // Player 1's first attack backed that challenge; another one against
+    // player 1 has to follow a later attack of theirs.
+    assert_eq!(
+        s.client
+            .try_challenge_unanswered(&session, &false, &Some(p1_first), &p2_first),
+        Err(Ok(Error::AttackNotOwed))
+    );
+    s.client
+        .challenge_unanswered(&session, &true, &Some(p2_first.clone()), &p1_second);
+    answer(2, t + 2_500);
+    assert_eq!(
+        s.client
+            .try_challenge_unanswered(&session, &true, &Some(p2_first), &p1_second),
+        Err(Ok(Error::AttackNotOwed))
+    );
+    s.client
+        .challenge_unanswered(&session, &false, &Some(p1_second), &p2_second);
+    assert_eq!(s.client.get_challenge(&session).unwrap().row, 3);
+}
+
+#[test]
+fn bind_match_rules() {
+    let s = setup();
+    let session = s.bound_match();
+    let key = public_key(&s.env, &s.player1);
+    assert_eq!(
+        s.client
+            .try_bind_match(&session, &String::from_str(&s.env, MATCH_ID), &key, &key),
+        Err(Ok(Error::MatchAlreadyBound))
+    );
+    let server = Address::generate(&s.env);
+    let session = s.client.open_match(&server, &server);
+    for bad in ["", "quote\"d", &"x".repeat(65)] {
+        assert_eq!(
+            s.client
+                .try_bind_match(&session, &String::from_str(&s.env, bad), &key, &key),
+            Err(Ok(Error::InvalidMatchId)),
+            "{bad:?}"
+        );
+    }
+    let miss = shot_result(&s.env, &s.player1, 0, 0, false, 0);
+    assert_eq!(
+        s.client
+            .try_dispute_contradiction(&session, &true, &miss, &miss),
+        Err(Ok(Error::MatchNotBound))
+    );
+}
+
+/// Player 2's board hash as the web client signs it.
+const BOARD_HASH: &str = "0x1e5b7f2c9a0d34e6b18c5f7a2d903b4e6c1f8a7d2e5b09c3f4a6d8e1b2c7f903";
+
+/// `shot_proof` public inputs for `board_hash` (64 hex digits after `0x`).
+fn shot_inputs(env: &Env, board_hash: &str, row: u32, col: u32, is_hit: u32) -> Bytes {
+    let mut inputs = [0u8; 128];
+    for (i, byte) in inputs[..32].iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&board_hash[2 + 2 * i..4 + 2 * i], 16).unwrap();
+    }
+    for (field, value) in [(1, row), (2, col), (3, is_hit)] {
+        inputs[field * 32 + 28..(field + 1) * 32].copy_from_slice(&value.to_be_bytes());
+    }
+    Bytes::from_slice(env, &inputs)
+}
+
+fn proof(env: &Env) -> Bytes {
+    Bytes::from_slice(env, &[1u8; 64])
+}
+
+#[test]
+fn shot_proof_contradicting_a_signed_result_ends_the_match() {
+    let s = setup();
+    let session = s.bound_match();
+    s.client.register_board_hash(
+        &session,
+        &false,
+        &placement(&s.env, &s.player2, BOARD_HASH, 1),
+    );
+    let claimed = shot_result(&s.env, &s.player2, 3, 4, false, 1_760_000_000_000);
+
+    // A proof that doesn't verify, or that agrees with the answer, settles
+    // nothing.
+    let hit = shot_inputs(&s.env, BOARD_HASH, 3, 4, 1);
+    let empty = Bytes::new(&s.env);
+    assert_eq!(
+        s.client
+            .try_dispute_shot(&session, &false, &3, &4, &claimed, &empty, &hit),
+        Err(Ok(Error::VerificationFailed))
+    );
+    let miss = shot_inputs(&s.env, BOARD_HASH, 3, 4, 0);
+    assert_eq!(
+        s.client
+            .try_dispute_shot(&session, &false, &3, &4, &claimed, &proof(&s.env), &miss),
+        Err(Ok(Error::NotAContradiction))
+    );
+    assert!(!s.client.get_match(&session).closed);
+
+    s.client
+        .dispute_shot(&session, &false, &3, &4, &claimed, &proof(&s.env), &hit);
+    assert!(s.client.get_match(&session).closed);
+    assert_eq!(s.hub.result(&session), Some(true));
+}
+
+#[test]
+fn dispute_shot_rejects_mismatched_inputs() {
+    let s = setup();
+    let session = s.bound_match();
+    let claimed = shot_result(&s.env, &s.player2, 3, 4, false, 1_760_000_000_000);
+    let hit = shot_inputs(&s.env, BOARD_HASH, 3, 4, 1);
+    let proof = proof(&s.env);
+    assert_eq!(
+        s.client
+            .try_dispute_shot(&session, &false, &3, &4, &claimed, &proof, &hit),
+        Err(Ok(Error::BoardNotRegistered))
+    );
+    s.client.register_board_hash(
+        &session,
+        &false,
+        &placement(&s.env, &s.player2, BOARD_HASH, 1),
+    );
+
+    assert_eq!(
+        s.client
+            .try_dispute_shot(&session, &false, &3, &5, &claimed, &proof, &hit),
+        Err(Ok(Error::CellMismatch))
+    );
+    let other_cell = shot_inputs(&s.env, BOARD_HASH, 4, 3, 1);
+    assert_eq!(
+        s.client
+            .try_dispute_shot(&session, &false, &3, &4, &claimed, &proof, &other_cell),
+        Err(Ok(Error::CellMismatch))
+    );
+    let not_a_bit = shot_inputs(&s.env, BOARD_HASH, 3, 4, 2);
+    assert_eq!(
+        s.client
+            .try_dispute_shot(&session, &false, &3, &4, &claimed, &proof, &not_a_bit),
+        Err(Ok(Error::InvalidPublicInputs))
+    );
+    let truncated = hit.slice(..96);
+    assert_eq!(
+        s.client
+            .try_dispute_shot(&session, &false, &3, &4, &claimed, &proof, &truncated),
+        Err(Ok(Error::InvalidPublicInputs))
+    );
+    // The answer must be the defender's own.
+    let by_attacker = shot_result(&s.env, &s.player1, 3, 4, false, 1_760_000_000_000);
+    assert!(s
+        .client
+        .try_dispute_shot(&session, &false, &3, &4, &by_attacker, &proof, &hit)
+        .is_err());
+    // And the proof must be for the defender's own board.
+    assert_eq!(
+        s.client
+            .try_dispute_shot(&session, &true, &3, &4, &by_attacker, &proof, &hit),
+        Err(Ok(Error::BoardNotRegistered))
+    );
+    s.client
+        .register_board_hash(&session, &true, &placement(&s.env, &s.player1, "0x2a", 1));
+    assert_eq!(
+        s.client
+            .try_dispute_shot(&session, &true, &3, &4, &by_attacker, &proof, &hit),
+        Err(Ok(Error::BoardHashMismatch))
+    );
+    assert!(!s.client.get_match(&session).closed);
+}
+
+/// A player who registers the opponent's board hash as their own can't
+/// shield the opponent from a shot dispute: the defender is named, not
+/// looked up by hash.
+#[test]
+fn dispute_shot_checks_the_named_defender() {
+    let s = setup();
+    let session = s.bound_match();
+    for (player1, key) in [(true, &s.player1), (false, &s.player2)] {
+        s.client
+            .register_board_hash(&session, &player1, &placement(&s.env, key, BOARD_HASH, 1));
+    }
+    let claimed = shot_result(&s.env, &s.player2, 3, 4, false, 1_760_000_000_000);
+    let hit = shot_inputs(&s.env, BOARD_HASH, 3, 4, 1);
+    s.client
+        .dispute_shot(&session, &false, &3, &4, &claimed, &proof(&s.env), &hit);
+    assert!(s.client.get_match(&session).closed);
+    assert_eq!(s.hub.result(&session), Some(true));
+}
+
+#[test]
+fn board_hash_registration() {
+    let s = setup();
+    let session = s.bound_match();
+    // Hex without `0x` or leading zeros names the same field.
+    s.client
+        .register_board_hash(&session, &true, &placement(&s.env, &s.player1, "abc", 1));
+    let expected = {
+        let mut field = [0u8; 32];
+        field[30..].copy_from_slice(&[0x0a, 0xbc]);
+        BytesN::from_array(&s.env, &field)
+    };
+    assert_eq!(s.client.get_binding(&session).player1_board, Some(expected));
+    s.client
+        .register_board_hash(&session, &true, &placement(&s.env, &s.player1, "0xabc", 2));
+    assert!(!s.client.get_match(&session).closed);
+
+    for bad in ["", "0x", "0xxyz", &format!("0x{}", "1".repeat(65))] {
+        assert_eq!(
+            s.client.try_register_board_hash(
+                &session,
+                &false,
+                &placement(&s.env, &s.player2, bad, 1)
+            ),
+            Err(Ok(Error::InvalidBoardHash)),
+            "{bad:?}"
+        );
+    }
+    // Player 2 can't register a board for player 1.
+    assert!(s
+        .client
+        .try_register_board_hash(&session, &true, &placement(&s.env, &s.player2, "0x1", 1))
+        .is_err());
+
+    // Signing a second, different board is itself a lie.
+    s.client
+        .register_board_hash(&session, &true, &placement(&s.env, &s.player1, "0xabd", 3));
+    assert!(s.client.get_match(&session).closed);
+    assert_eq!(s.hub.result(&session), Some(false));
+}
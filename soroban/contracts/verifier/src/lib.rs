@@ -14,6 +14,7 @@ use ultrahonk_soroban_verifier::UltraHonkVerifier;
 pub enum CircuitType {
     BoardValidity = 0,
     TurnsProof = 1,
+    ShotProof = 2,
 }
 
 #[contracterror]
@@ -103,6 +104,20 @@ impl ZkVerifierContract {
         Ok(true)
     }
 
+    /// `VerificationKey::hash` of a circuit's VK, so callers can pin the exact
+    /// circuit version. Computed by the verifier, not read from bb's output.
+    pub fn vk_hash(env: Env, circuit: CircuitType) -> Result<BytesN<32>, VerifierError> {
+        let vk: Bytes = env
+            .storage()
+            .instance()
+            .get(&StorageKey::Vk(circuit))
+            .ok_or(VerifierError::VkNotSet)?;
+
+        let verifier =
+            UltraHonkVerifier::new(&env, &vk).map_err(|_| VerifierError::VkNotSet)?;
+        Ok(BytesN::from_array(&env, &verifier.vk_hash().to_bytes()))
+    }
+
     /// Verify a board_validity proof.
     pub fn verify_board(
         env: Env,
@@ -121,6 +136,15 @@ impl ZkVerifierContract {
         Self::verify_proof(env, CircuitType::TurnsProof, proof, public_inputs)
     }
 
+    /// Verify a shot_proof.
+    pub fn verify_shot(
+        env: Env,
+        proof: Bytes,
+        public_inputs: Bytes,
+    ) -> Result<bool, VerifierError> {
+        Self::verify_proof(env, CircuitType::ShotProof, proof, public_inputs)
+    }
+
     /// Check if a proof has been previously verified (by hash).
     pub fn is_proof_verified(env: Env, proof_hash: BytesN<32>) -> bool {
         env.storage()
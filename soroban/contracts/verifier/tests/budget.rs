@@ -0,0 +1,90 @@
+//! Budget of every `ZkVerifierContract` entry point, checked against
+//! `budget_baseline.json` (see `budget-harness`). The contract runs natively,
+//! so for the verify calls only host functions (BN254 point operations,
+//! keccak, storage) are metered, not the in-WASM field arithmetic.
+
+use budget_harness::Harness;
+use soroban_sdk::{testutils::Address as _, Address, Bytes, BytesN, Env};
+use std::{fs, path::Path};
+use zk_verifier::{CircuitType, ZkVerifierContract, ZkVerifierContractClient};
+
+const BASELINE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/budget_baseline.json");
+
+/// The game's board_validity VK, checked in under `soroban/vk`.
+const BOARD_VK: &[u8] = include_bytes!("../../../vk/board_validity_vk.bin");
+
+/// A proof that verifies, from the vendored verifier's test circuit
+/// (`vendor/ultrahonk-rust-verifier/tests/build_circuits.sh`).
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../vendor/ultrahonk-rust-verifier/tests/simple_circuit/target"
+);
+
+fn fixture(env: &Env, file: &str) -> Result<Bytes, String> {
+    let path = Path::new(FIXTURE).join(file);
+    let data = fs::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(Bytes::from_slice(env, &data))
+}
+
+#[test]
+fn admin_and_view_budgets() -> Result<(), String> {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vk = Bytes::from_slice(&env, BOARD_VK);
+
+    let mut harness = Harness::new(&env, BASELINE, "native");
+    let id = harness.measure("__constructor", || {
+        env.register(ZkVerifierContract, (&admin,))
+    });
+    let client = ZkVerifierContractClient::new(&env, &id);
+    harness.measure("set_verification_key", || {
+        client.set_verification_key(&admin, &CircuitType::BoardValidity, &vk)
+    });
+    harness.measure("vk_hash", || client.vk_hash(&CircuitType::BoardValidity));
+    let unknown = BytesN::from_array(&env, &[7u8; 32]);
+    assert!(!harness.measure("is_proof_verified", || client.is_proof_verified(&unknown)));
+
+    harness.finish()
+}
+
+#[test]
+fn verify_budgets() -> Result<(), String> {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let vk = fixture(&env, "vk")?;
+    let proof = fixture(&env, "proof")?;
+    let public_inputs = fixture(&env, "public_inputs")?;
+
+    let mut harness = Harness::new(&env, BASELINE, "native_verify");
+    let id = env.register(ZkVerifierContract, (&admin,));
+    let client = ZkVerifierContractClient::new(&env, &id);
+    for circuit in [
+        CircuitType::BoardValidity,
+        CircuitType::TurnsProof,
+        CircuitType::ShotProof,
+    ] {
+        client.set_verification_key(&admin, &circuit, &vk);
+    }
+    let verified = [
+        harness.measure("verify_proof", || {
+            client.verify_proof(&CircuitType::BoardValidity, &proof, &public_inputs)
+        }),
+        harness.measure("verify_board", || {
+            client.verify_board(&proof, &public_inputs)
+        }),
+        harness.measure("verify_turns", || {
+            client.verify_turns(&proof, &public_inputs)
+        }),
+        harness.measure("verify_shot", || {
+            client.verify_shot(&proof, &public_inputs)
+        }),
+    ];
+    assert_eq!(verified, [true; 4]);
+
+    let proof_hash: BytesN<32> = env.crypto().sha256(&proof).into();
+    assert!(client.is_proof_verified(&proof_hash));
+
+    harness.finish()
+}
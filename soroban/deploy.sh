@@ -9,7 +9,7 @@ WASM_DIR="${SCRIPT_DIR}/target/wasm32v1-none/release"
 GAME_HUB="CB4VZAT2U3UC6XFK3N23SKRF2NDCMP3QHJYMCHHFMZO7MRQO6DQ2EMYG"
 
 # Validate VK files exist
-for vk_file in board_validity_vk.bin turns_proof_vk.bin; do
+for vk_file in board_validity_vk.bin turns_proof_vk.bin shot_proof_vk.bin; do
   if [ ! -f "${VK_DIR}/${vk_file}" ]; then
     echo "ERROR: VK file not found: ${VK_DIR}/${vk_file}"
     echo "Run ./generate_vk.sh first"
@@ -86,6 +86,17 @@ stellar contract invoke \
   --circuit 1 \
   --vk_data "$(xxd -p < "${VK_DIR}/turns_proof_vk.bin" | tr -d '\n')"
 
+echo "  Setting shot_proof VK..."
+stellar contract invoke \
+  --id "$VERIFIER_ID" \
+  --source-account "$STELLAR_SERVER_SECRET" \
+  --network testnet \
+  -- \
+  set_verification_key \
+  --admin "$ADMIN" \
+  --circuit 2 \
+  --vk_data "$(xxd -p < "${VK_DIR}/shot_proof_vk.bin" | tr -d '\n')"
+
 echo ""
 
 # ─── 2. Deploy Battleship ───
@@ -9,7 +9,7 @@ VK_DIR="${SCRIPT_DIR}/vk"
 
 mkdir -p "$VK_DIR"
 
-CIRCUITS=("board_validity" "turns_proof")
+CIRCUITS=("board_validity" "turns_proof" "shot_proof")
 
 for circuit in "${CIRCUITS[@]}"; do
   echo "=== Generating VK for ${circuit} ==="